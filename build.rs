@@ -0,0 +1,91 @@
+//! Reads the FIX data dictionary in `dictionary/` and generates `tags` and
+//! `msgdefs` modules into `$OUT_DIR/generated.rs`, which `src/lib.rs` pulls
+//! in with `include!`. `tags` gives every field a named `i32` constant, and
+//! `msgdefs` gives every `MsgType` its required/optional tag lists, so
+//! adding a field, or adding a required tag to a message whose decoder runs
+//! `check_required`, is a dictionary edit, not a Rust change. The
+//! `decode`/`encode_body` impl for each message type is hand-written in
+//! `src/lib.rs` (see `dictionary/messages.in`): a tag a decoder reads stays
+//! required whatever its dictionary line says, `NewOrder` and `Fill` do not
+//! read `msgdefs` at all, and a brand new `MsgType` needs its own impl.
+
+use std::env;
+use std::fs;
+use std::path::Path;
+
+fn main() {
+  let out_dir = env::var("OUT_DIR").unwrap();
+  let dest = Path::new(&out_dir).join("generated.rs");
+
+  let fields_src = fs::read_to_string("dictionary/fields.in").expect("read dictionary/fields.in");
+  let messages_src = fs::read_to_string("dictionary/messages.in").expect("read dictionary/messages.in");
+
+  let mut out = String::new();
+  out.push_str(&generate_tags(&fields_src));
+  out.push_str(&generate_msgdefs(&messages_src));
+  fs::write(&dest, out).expect("write generated.rs");
+
+  println!("cargo:rerun-if-changed=dictionary/fields.in");
+  println!("cargo:rerun-if-changed=dictionary/messages.in");
+  println!("cargo:rerun-if-changed=build.rs");
+}
+
+/// Emits `pub mod tags { pub const Name: i32 = tag; ... }` from `fields.in`.
+fn generate_tags(src: &str) -> String {
+  let mut out = String::new();
+  out.push_str("#[allow(non_upper_case_globals)]\n");
+  out.push_str("pub mod tags {\n");
+  for line in src.lines() {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+      continue;
+    }
+    let mut parts = line.split_whitespace();
+    let tag = parts.next().expect("field line missing tag");
+    let name = parts.next().expect("field line missing name");
+    out.push_str(&format!("  pub const {}: i32 = {};\n", name, tag));
+  }
+  out.push_str("}\n\n");
+  out
+}
+
+/// Emits `pub mod msgdefs { pub struct MsgDef {..} pub const NAME: MsgDef = ..; }`
+/// from `messages.in`, one `MsgDef` per `MsgType` giving its required and
+/// optional field lists.
+fn generate_msgdefs(src: &str) -> String {
+  let mut out = String::new();
+  out.push_str("pub mod msgdefs {\n");
+  out.push_str("  #[derive(Debug, Clone, Copy)]\n");
+  out.push_str("  pub struct MsgDef {\n");
+  out.push_str("    pub msg_type: &'static str,\n");
+  out.push_str("    pub required: &'static [i32],\n");
+  out.push_str("    pub optional: &'static [i32],\n");
+  out.push_str("  }\n\n");
+  for line in src.lines() {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+      continue;
+    }
+    let mut parts = line.split_whitespace();
+    let msg_type = parts.next().expect("message line missing MsgType");
+    let name = parts.next().expect("message line missing struct name");
+    let mut required = "";
+    let mut optional = "";
+    for rest in parts {
+      if let Some(tags) = rest.strip_prefix("required:") {
+        required = tags;
+      } else if let Some(tags) = rest.strip_prefix("optional:") {
+        optional = tags;
+      }
+    }
+    out.push_str(&format!(
+      "  pub const {}: MsgDef = MsgDef {{ msg_type: \"{}\", required: &[{}], optional: &[{}] }};\n",
+      name.to_uppercase(),
+      msg_type,
+      required,
+      optional,
+    ));
+  }
+  out.push_str("}\n");
+  out
+}
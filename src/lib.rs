@@ -1,42 +1,206 @@
 //! # FIX crate
 //!
-//! `fix_crate` contains a FIX server and FIX client
+//! `fix_crate` contains a FIX server and FIX client.
+//!
+//! `std` is on by default (`default = ["std"]`) and brings in `chrono` for
+//! wall-clock `SendingTime` stamping plus the `session` client, which needs
+//! `std::io`/`std::time`. Built with `--no-default-features`, the crate is
+//! `no_std` + `alloc`: parsing and serialization still work (`BTreeMap`
+//! stands in for `HashMap`), and callers supply `SendingTime` themselves by
+//! implementing `Clock`.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(not(feature = "std"))]
+extern crate alloc;
 
-use std::collections::HashMap;
+#[cfg(feature = "std")]
+use std::collections::HashMap as FixMap;
+#[cfg(not(feature = "std"))]
+use alloc::collections::BTreeMap as FixMap;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+#[cfg(not(feature = "std"))]
+use alloc::string::String;
+#[cfg(not(feature = "std"))]
+use alloc::string::ToString;
+#[cfg(not(feature = "std"))]
+use alloc::format;
+#[cfg(all(test, not(feature = "std")))]
+use alloc::vec;
 // use std::error::Error;
-use std::str;
-use std::fmt;
-use std::io::{Cursor, Write};
-
-use chrono::Utc;
-
-#[allow(non_upper_case_globals)]
-pub mod tags {
-  pub const MsgType: i32 = 35;
-  pub const MsgSeqNum: i32 = 34;
-  pub const SenderCompID: i32 = 49;
-  pub const TargetCompID: i32 = 56;
-  pub const ExecType: i32 = 150;
-  pub const Symbol: i32 = 55;
-  pub const Price: i32 = 44;
-  pub const ClOrdId: i32 = 11;
-  pub const OrigClOrdId: i32 = 41;
-  pub const OrderID: i32 = 37;
-  pub const OrdStatus: i32 = 39;
-  pub const OrderQty: i32 = 38;
-  pub const Side: i32 = 54;
-  pub const ExecTransType: i32 = 20;
-  pub const LastPx: i32 = 31;
-  pub const LastShares: i32 = 32;
-  pub const LeavesQty: i32 = 151;
-  pub const ExecID: i32 = 17;
-  pub const BeginString: i32 = 8;
-  pub const BodyLength: i32 = 9;
-  pub const CheckSum: i32 = 10;
-  pub const Text: i32 = 58;
-  pub const EndSeqNo: i32 = 16;
-  pub const GapFillFlag: i32 = 123;
-  pub const NewSeqNo: i32 = 36;
+use core::str;
+use core::fmt;
+
+/// A minimal `Write`/`Cursor` pair so `serialize_head` and
+/// `cursor::CursorMut` can build FIX bytes into a `&mut [u8]` the same way
+/// whether or not `std::io` is available. Under `std` these are just
+/// `std::io`'s own types; under `alloc`-only they're a small
+/// `core::fmt::Write` shim over a byte slice.
+#[cfg(feature = "std")]
+mod io_compat {
+  pub use std::io::{Cursor, Write};
+}
+
+#[cfg(not(feature = "std"))]
+mod io_compat {
+  use core::fmt;
+
+  pub use core::fmt::Write;
+
+  pub struct Cursor<T> {
+    inner: T,
+    pos: usize,
+  }
+
+  impl<'a> Cursor<&'a mut [u8]> {
+    pub fn new(inner: &'a mut [u8]) -> Self {
+      Cursor { inner, pos: 0 }
+    }
+
+    pub fn position(&self) -> u64 {
+      self.pos as u64
+    }
+
+    pub fn into_inner(self) -> &'a mut [u8] {
+      self.inner
+    }
+  }
+
+  impl fmt::Write for Cursor<&mut [u8]> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+      let bytes = s.as_bytes();
+      let end = self.pos + bytes.len();
+      if end > self.inner.len() {
+        return Err(fmt::Error);
+      }
+      self.inner[self.pos..end].copy_from_slice(bytes);
+      self.pos = end;
+      Ok(())
+    }
+  }
+}
+
+use io_compat::{Cursor, Write};
+
+/// Supplies the FIX `SendingTime` (tag 52) value that `serialize_head`
+/// stamps into every message header.
+///
+/// The `std` feature provides `ChronoClock`, backed by `chrono::Utc::now`;
+/// `no_std` callers without a wall clock (firmware, WASM sandboxes) implement
+/// this themselves, e.g. reading a hardware RTC or a host-injected
+/// timestamp.
+pub trait Clock {
+  /// Writes the `SendingTime` value (`YYYYMMDD-HH:MM:SS.sss`) into `buf`,
+  /// returning the written slice.
+  fn sending_time<'a>(&self, buf: &'a mut [u8]) -> &'a str;
+}
+
+/// The default `Clock`: stamps the current UTC wall-clock time via `chrono`.
+#[cfg(feature = "std")]
+pub struct ChronoClock;
+
+#[cfg(feature = "std")]
+impl Clock for ChronoClock {
+  fn sending_time<'a>(&self, buf: &'a mut [u8]) -> &'a str {
+    let dt = chrono::Utc::now();
+    let mut cur = Cursor::new(buf);
+    write!(cur, "{}", dt.format("%Y%m%d-%T%.3f")).unwrap();
+    let len = cur.position() as usize;
+    str::from_utf8(&cur.into_inner()[..len]).unwrap()
+  }
+}
+
+// `tags` and `msgdefs` are code-generated at build time from the FIX data
+// dictionary in `dictionary/` (see build.rs): `tags` names every field's
+// number and `msgdefs` lists each MsgType's required/optional tags. Adding a
+// field, or a required tag for a message whose decoder runs
+// `check_required`, is a dictionary edit. The decode/encode_body impl for
+// each message type is hand-written below: a tag a decoder reads stays
+// required whatever the dictionary says, and `NewOrder`/`Fill` do not read
+// `msgdefs` (see dictionary/messages.in).
+include!(concat!(env!("OUT_DIR"), "/generated.rs"));
+
+/// Checks that every tag in `def.required` is present in `m`, returning the
+/// first one that's missing.
+fn check_required(m: &FixMap<i32, &str>, def: &msgdefs::MsgDef) -> Result<(), FixError> {
+  for &tag in def.required {
+    if !m.contains_key(&tag) {
+      return Err(FixError{ kind: FixErrorKind::MissingField, field: tag });
+    }
+  }
+  Ok(())
+}
+
+/// Streaming field cursors over a raw `tag=value\x01` FIX buffer.
+///
+/// `to_fix_hash` allocates a `FixMap<i32, &str>` for every message and
+/// destroys wire order doing it; `Cursor`/`CursorMut` walk the buffer in
+/// place instead, which is what the hot New/Fill path (`NewOrder::new`,
+/// `Fill::new`) and `serialize_body` use.
+pub mod cursor {
+  use core::str;
+  use super::io_compat::{Cursor as ByteCursor, Write};
+  use super::{FixError, FixErrorKind};
+
+  /// Walks a `&[u8]` one `tag=value` pair at a time without allocating.
+  pub struct Cursor<'a> {
+    buf: &'a [u8],
+    pos: usize,
+  }
+
+  impl<'a> Cursor<'a> {
+    pub fn new(buf: &'a [u8]) -> Self {
+      Cursor { buf, pos: 0 }
+    }
+
+    pub fn is_empty(&self) -> bool {
+      self.pos >= self.buf.len()
+    }
+
+    /// Reads the next `tag=value` pair, advancing past its trailing SOH.
+    pub fn get_key_value(&mut self) -> Result<(i32, &'a str), FixError> {
+      if self.is_empty() {
+        return Err(FixError{ kind: FixErrorKind::Incomplete, field: 0 });
+      }
+      let rest = &self.buf[self.pos..];
+      let eq = rest.iter().position(|&b| b == b'=')
+        .ok_or(FixError{ kind: FixErrorKind::InvalidFormat, field: 0 })?;
+      let tag : i32 = str::from_utf8(&rest[..eq]).ok()
+        .and_then(|s| s.parse().ok())
+        .ok_or(FixError{ kind: FixErrorKind::InvalidFormat, field: 0 })?;
+      let after_eq = &rest[eq + 1..];
+      let soh = after_eq.iter().position(|&b| b == 0x01)
+        .ok_or(FixError{ kind: FixErrorKind::Incomplete, field: tag })?;
+      let value = str::from_utf8(&after_eq[..soh])
+        .map_err(|_| FixError{ kind: FixErrorKind::InvalidFormat, field: tag })?;
+      self.pos += eq + 1 + soh + 1;
+      Ok((tag, value))
+    }
+  }
+
+  /// Builds a `tag=value\x01` buffer directly into a borrowed buffer, the
+  /// `put_key_value` counterpart to `Cursor::get_key_value`. Used by
+  /// `serialize_body` instead of collecting fields into a `HashMap` first.
+  pub struct CursorMut<'a> {
+    buf: &'a mut [u8],
+    pos: usize,
+  }
+
+  impl<'a> CursorMut<'a> {
+    pub fn new(buf: &'a mut [u8]) -> Self {
+      CursorMut { buf, pos: 0 }
+    }
+
+    pub fn put_key_value(&mut self, tag: i32, value: &str) {
+      let mut dst = ByteCursor::new(&mut self.buf[self.pos..]);
+      write!(dst, "{}={}\x01", tag, value).expect("buffer too small");
+      self.pos += dst.position() as usize;
+    }
+
+    pub fn into_inner(self) -> &'a [u8] {
+      &self.buf[..self.pos]
+    }
+  }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -46,6 +210,8 @@ pub enum FixErrorKind {
   MissingField,
   InvalidFormat,
   UnexpectedMessage,
+  BadCheckSum,
+  BadBodyLength,
 }
 
 #[derive(Debug, Clone)]
@@ -67,35 +233,79 @@ impl fmt::Display for FixError {
         write!(f, "Message field #{} has an invalid format", self.field),
       FixErrorKind::UnexpectedMessage =>
         write!(f, "Message kind is unexpected"),
+      FixErrorKind::BadCheckSum =>
+        write!(f, "Message field #{} (CheckSum) does not match the computed checksum", self.field),
+      FixErrorKind::BadBodyLength =>
+        write!(f, "Message field #{} (BodyLength) does not match the actual body length", self.field),
     }
   }
 }
 
-fn get_or_fail(m: &HashMap<i32, &str>, field : i32) -> Result<String, FixError> {
-  m.get(&field).map(|s| s.to_string()).ok_or(FixError{ kind: FixErrorKind::MissingField, field: field})
+/// Parses `Self` out of a full raw FIX message (header through trailer), so
+/// the `parse` dispatch ladder can construct every message type the same
+/// way instead of calling a different bespoke `new`/`HashMap` builder per
+/// arm.
+pub trait FixDecode<'a>: Sized {
+  fn decode(fixmsg: &'a str) -> Result<Self, FixError>;
+}
+
+/// Writes `Self`'s body fields (everything but the session header/trailer,
+/// which `serialize_head`/`serialize` already own) into a `CursorMut`.
+pub trait FixEncode {
+  fn msg_type(&self) -> &'static str;
+  fn encode_body(&self, cur: &mut cursor::CursorMut<'_>) -> Result<(), FixError>;
+}
+
+fn get_or_fail(m: &FixMap<i32, &str>, field : i32) -> Result<String, FixError> {
+  m.get(&field).map(|s| s.to_string()).ok_or(FixError{ kind: FixErrorKind::MissingField, field})
 }
 
 #[derive(Debug)]
-pub struct NewOrder {
-    pub symbol: String,
-    pub clordid: String,
+pub struct NewOrder<'a> {
+    pub symbol: &'a str,
+    pub clordid: &'a str,
     pub price: i32,
     pub qty: i32,
     pub side: char,
 }
-impl NewOrder {
-  pub fn new(m: &HashMap<i32, &str>) -> Result<NewOrder, FixError> {
-    let symbol = get_or_fail(m, tags::Symbol)?;
-    let clordid = get_or_fail(m, tags::ClOrdId)?;
-    let price  = get_or_fail(m, tags::Price)?;
-    let price : f64 = price.parse().unwrap();
-    let price = price * 10000.0;
-    let price = price as i32;
-    let side = get_or_fail(m, tags::Side)?;
-    let side = if "1" == side { 'B' } else { 'S' };
-    let qty = get_or_fail(m, tags::OrderQty)?;
-    let qty : i32 = qty.parse().unwrap();
-    return Ok(NewOrder{symbol, clordid, price, qty, side})
+impl<'a> NewOrder<'a> {
+  /// Parses a `NewOrder` straight out of the raw wire bytes with a
+  /// `cursor::Cursor`, borrowing `symbol`/`clordid` instead of allocating a
+  /// `FixMap<i32, &str>` first.
+  pub fn new(buf: &'a [u8]) -> Result<NewOrder<'a>, FixError> {
+    let mut cur = cursor::Cursor::new(buf);
+    let mut symbol = None;
+    let mut clordid = None;
+    let mut price = None;
+    let mut qty = None;
+    let mut side = None;
+    while !cur.is_empty() {
+      let (tag, value) = cur.get_key_value()?;
+      if tag == tags::Symbol {
+        symbol = Some(value);
+      } else if tag == tags::ClOrdId {
+        clordid = Some(value);
+      } else if tag == tags::Price {
+        let p : f64 = value.parse().map_err(|_| FixError{kind: FixErrorKind::InvalidFormat, field: tags::Price})?;
+        price = Some((p * 10000.0) as i32);
+      } else if tag == tags::OrderQty {
+        qty = Some(value.parse().map_err(|_| FixError{kind: FixErrorKind::InvalidFormat, field: tags::OrderQty})?);
+      } else if tag == tags::Side {
+        side = Some(if value == "1" { 'B' } else { 'S' });
+      }
+    }
+    Ok(NewOrder{
+      symbol: symbol.ok_or(FixError{kind: FixErrorKind::MissingField, field: tags::Symbol})?,
+      clordid: clordid.ok_or(FixError{kind: FixErrorKind::MissingField, field: tags::ClOrdId})?,
+      price: price.ok_or(FixError{kind: FixErrorKind::MissingField, field: tags::Price})?,
+      qty: qty.ok_or(FixError{kind: FixErrorKind::MissingField, field: tags::OrderQty})?,
+      side: side.ok_or(FixError{kind: FixErrorKind::MissingField, field: tags::Side})?,
+    })
+  }
+}
+impl<'a> FixDecode<'a> for NewOrder<'a> {
+  fn decode(fixmsg: &'a str) -> Result<Self, FixError> {
+    NewOrder::new(fixmsg.as_bytes())
   }
 }
 #[derive(Debug)]
@@ -104,83 +314,216 @@ pub struct CancelOrder {
     pub origclordid: String,
 }
 impl CancelOrder {
-  fn new(m : &HashMap<i32, &str>) -> Result<CancelOrder, FixError> {
+  fn new(m : &FixMap<i32, &str>) -> Result<CancelOrder, FixError> {
+    check_required(m, &msgdefs::CANCELORDER)?;
     let clordid = get_or_fail(m, tags::ClOrdId)?;
     let origclordid = get_or_fail(m, tags::OrigClOrdId)?;
-    return Ok(CancelOrder{clordid, origclordid});
+    Ok(CancelOrder{clordid, origclordid})
+  }
+}
+impl<'a> FixDecode<'a> for CancelOrder {
+  fn decode(fixmsg: &'a str) -> Result<Self, FixError> {
+    CancelOrder::new(&to_fix_hash(fixmsg)?)
   }
 }
 #[derive(Debug)]
 pub struct NewOrderAck {
-    // symbol: Option<String>,
-    // clordid: String,
+    pub clordid: String,
+    pub orderid: String,
+    pub symbol: String,
+    pub price: i32,
+    pub qty: i32,
+    pub side: char,
 }
 impl NewOrderAck {
-  fn new(_m: &HashMap<i32, &str>) -> Result<NewOrderAck, FixError> {
-    // let clordid = get_or_fail(m, tags::ClOrdId)?;
-    // return Ok(NewOrderAck{symbol: m.get(&tags::Symbol).map(|s| s.to_string()), clordid: clordid});
-    return Ok(NewOrderAck{});
+  fn new(m: &FixMap<i32, &str>) -> Result<NewOrderAck, FixError> {
+    check_required(m, &msgdefs::NEWORDERACK)?;
+    let clordid = get_or_fail(m, tags::ClOrdId)?;
+    let orderid = get_or_fail(m, tags::OrderID)?;
+    let symbol = get_or_fail(m, tags::Symbol)?;
+    let price = get_or_fail(m, tags::Price)?;
+    let price : f64 = price.parse().map_err(|_| FixError{kind: FixErrorKind::InvalidFormat, field: tags::Price})?;
+    let qty = get_or_fail(m, tags::OrderQty)?;
+    let qty : i32 = qty.parse().map_err(|_| FixError{kind: FixErrorKind::InvalidFormat, field: tags::OrderQty})?;
+    let side = get_or_fail(m, tags::Side)?;
+    let side = if side == "1" { 'B' } else { 'S' };
+    Ok(NewOrderAck{clordid, orderid, symbol, price: (price * 10000.0) as i32, qty, side})
   }
+
+  #[cfg(feature = "std")]
+  #[allow(clippy::too_many_arguments)]
   pub fn serialize(sendercompid: &str, targetcompid: &str, seqno: u32, clordid: &str, orderid: &str, symbol: &str, price: i32, qty: i32, side: char) -> Vec<u8> {
-    let price = (price as f64) / 10000.0;
-    let price = format!("{:.4}", price);
-    let qty = qty.to_string();
-    let side = side.to_string();
-    let fields : HashMap<i32, &str> = vec![(tags::ClOrdId, clordid), (tags::OrderID, orderid), (tags::ExecTransType, "0"), (tags::OrdStatus, "0"), (tags::ExecType, "0"), (tags::Symbol, symbol), (tags::Price, &price), (tags::OrderQty, &qty), (tags::Side, &side)].into_iter().collect();
-    serialize("8", sendercompid, targetcompid, seqno, &fields)
+    let ack = NewOrderAck{
+      clordid: clordid.to_string(), orderid: orderid.to_string(), symbol: symbol.to_string(), price, qty, side,
+    };
+    encode(&ack, sendercompid, targetcompid, seqno)
+  }
+}
+impl<'a> FixDecode<'a> for NewOrderAck {
+  fn decode(fixmsg: &'a str) -> Result<Self, FixError> {
+    NewOrderAck::new(&to_fix_hash(fixmsg)?)
+  }
+}
+impl FixEncode for NewOrderAck {
+  fn msg_type(&self) -> &'static str { "8" }
+  fn encode_body(&self, cur: &mut cursor::CursorMut<'_>) -> Result<(), FixError> {
+    let price = format!("{:.4}", (self.price as f64) / 10000.0);
+    let qty = self.qty.to_string();
+    let side = self.side.to_string();
+    cur.put_key_value(tags::ClOrdId, &self.clordid);
+    cur.put_key_value(tags::OrderID, &self.orderid);
+    cur.put_key_value(tags::ExecTransType, "0");
+    cur.put_key_value(tags::OrdStatus, "0");
+    cur.put_key_value(tags::ExecType, "0");
+    cur.put_key_value(tags::Symbol, &self.symbol);
+    cur.put_key_value(tags::Price, &price);
+    cur.put_key_value(tags::OrderQty, &qty);
+    cur.put_key_value(tags::Side, &side);
+    Ok(())
   }
 }
 #[derive(Debug)]
 pub struct CancelOrderAck {
+    pub clordid: String,
+    pub origclordid: String,
+    pub orderid: String,
     pub symbol: String,
-    pub clorid: u64,
 }
 impl CancelOrderAck {
+  #[cfg(feature = "std")]
   pub fn serialize(sendercompid: &str, targetcompid: &str, seqno: u32, clordid: &str, origclordid: &str, orderid: &str, symbol: &str) -> Vec<u8> {
-    let fields : HashMap<i32, &str> = vec![(tags::ClOrdId, clordid), (tags::OrigClOrdId, origclordid), (tags::OrderID, orderid), (tags::ExecTransType, "0"), (tags::OrdStatus, "4"), (tags::ExecType, "4"), (tags::Symbol, symbol)].into_iter().collect();
-    serialize("8", sendercompid, targetcompid, seqno, &fields)
+    let ack = CancelOrderAck{
+      clordid: clordid.to_string(), origclordid: origclordid.to_string(), orderid: orderid.to_string(), symbol: symbol.to_string(),
+    };
+    encode(&ack, sendercompid, targetcompid, seqno)
+  }
+}
+impl FixEncode for CancelOrderAck {
+  fn msg_type(&self) -> &'static str { "8" }
+  fn encode_body(&self, cur: &mut cursor::CursorMut<'_>) -> Result<(), FixError> {
+    cur.put_key_value(tags::ClOrdId, &self.clordid);
+    cur.put_key_value(tags::OrigClOrdId, &self.origclordid);
+    cur.put_key_value(tags::OrderID, &self.orderid);
+    cur.put_key_value(tags::ExecTransType, "0");
+    cur.put_key_value(tags::OrdStatus, "4");
+    cur.put_key_value(tags::ExecType, "4");
+    cur.put_key_value(tags::Symbol, &self.symbol);
+    Ok(())
   }
 }
 #[derive(Debug)]
 pub struct OrderReject {
   pub symbol: String,
   pub clordid: String,
+  pub text: String,
 }
 impl OrderReject {
+  #[cfg(feature = "std")]
   pub fn serialize(sendercompid: &str, targetcompid: &str, seqno: u32, clordid: &str, symbol: &str, text: &str) -> Vec<u8> {
-    let fields : HashMap<i32, &str> = vec![(tags::ClOrdId, clordid), (tags::ExecTransType, "0"), (tags::OrdStatus, "8"), (tags::ExecType, "8"), (tags::Symbol, symbol), (tags::Text, text)].into_iter().collect();
-    serialize("8", sendercompid, targetcompid, seqno, &fields)
+    let reject = OrderReject{ clordid: clordid.to_string(), symbol: symbol.to_string(), text: text.to_string() };
+    encode(&reject, sendercompid, targetcompid, seqno)
+  }
+}
+impl FixEncode for OrderReject {
+  fn msg_type(&self) -> &'static str { "8" }
+  fn encode_body(&self, cur: &mut cursor::CursorMut<'_>) -> Result<(), FixError> {
+    cur.put_key_value(tags::ClOrdId, &self.clordid);
+    cur.put_key_value(tags::ExecTransType, "0");
+    cur.put_key_value(tags::OrdStatus, "8");
+    cur.put_key_value(tags::ExecType, "8");
+    cur.put_key_value(tags::Symbol, &self.symbol);
+    cur.put_key_value(tags::Text, &self.text);
+    Ok(())
   }
 }
 #[derive(Debug)]
-pub struct Fill {
-    pub symbol: String,
-    pub clorid: String,
+pub struct Fill<'a> {
+    pub symbol: &'a str,
+    pub clorid: &'a str,
     pub exec_price: i32,
     pub exec_qty: i32,
     pub side: char,
     pub aggr_ind: char,
+    // Only populated when building an outbound ack via `serialize`; absent
+    // on a `Fill` decoded from an inbound execution report.
+    pub orderid: Option<&'a str>,
+    pub execid: Option<u64>,
+    pub leaves_qty: Option<i32>,
 }
-impl Fill {
-  pub fn new(m: &HashMap<i32, &str>) -> Result<Fill, FixError> {
-    let symbol = get_or_fail(m, tags::Symbol)?;
-    let clordid = get_or_fail(m, tags::ClOrdId)?;
-    let exec_price = get_or_fail(m, 31)?;
-    let exec_price : f64 = exec_price.parse().map_err(|_| FixError{kind: FixErrorKind::InvalidFormat, field: 31})?;
-    let exec_price = (exec_price * 10000.0) as i32;
-    let exec_qty = m.get(&tags::LastShares).ok_or(FixError{kind:FixErrorKind::MissingField, field:tags::LastShares})?;
-    let exec_qty : i32 = exec_qty.parse().map_err(|_| FixError{ kind: FixErrorKind::InvalidFormat, field: tags::LastShares})?;
-    let side = m.get(&tags::Side).ok_or(FixError{kind:FixErrorKind::MissingField, field:tags::Side})?;
-    let side = if &"1" == side { 'B' } else { 'S' };
-    return Ok(Fill{symbol: symbol, clorid: clordid, exec_price: exec_price, exec_qty: exec_qty, side: side, aggr_ind: 'A'});
-  }
-  pub fn serialize(sendercompid: &str, targetcompid: &str, seqno: u32, clordid: &str, orderid: &str, symbol: &str, execid: u64, exec_price: i32, exec_qty: i32, leaves_qty: i32, _side: char) -> Vec<u8> {
-    let tipe = if leaves_qty == 0 { "2" } else { "1" };
-    let execid = execid.to_string();
-    let exec_price = ((exec_price as f64)/10000.0).to_string();
-    let exec_qty = exec_qty.to_string();
-    let fields : HashMap<i32, &str> = vec![(tags::ClOrdId, clordid), (tags::OrderID, orderid), (tags::ExecTransType, "0"), (tags::OrdStatus, tipe), (tags::ExecType, tipe), (tags::Symbol, symbol), (tags::ExecID, &execid), (tags::LastPx, &exec_price), (tags::LastShares, &exec_qty) ].into_iter().collect();
-    serialize("8", sendercompid, targetcompid, seqno, &fields)
+impl<'a> Fill<'a> {
+  /// Parses a `Fill` straight out of the raw wire bytes with a
+  /// `cursor::Cursor`, borrowing `symbol`/`clorid` instead of allocating a
+  /// `FixMap<i32, &str>` first.
+  pub fn new(buf: &'a [u8]) -> Result<Fill<'a>, FixError> {
+    let mut cur = cursor::Cursor::new(buf);
+    let mut symbol = None;
+    let mut clorid = None;
+    let mut exec_price = None;
+    let mut exec_qty = None;
+    let mut side = None;
+    while !cur.is_empty() {
+      let (tag, value) = cur.get_key_value()?;
+      if tag == tags::Symbol {
+        symbol = Some(value);
+      } else if tag == tags::ClOrdId {
+        clorid = Some(value);
+      } else if tag == tags::LastPx {
+        let p : f64 = value.parse().map_err(|_| FixError{kind: FixErrorKind::InvalidFormat, field: tags::LastPx})?;
+        exec_price = Some((p * 10000.0) as i32);
+      } else if tag == tags::LastShares {
+        exec_qty = Some(value.parse().map_err(|_| FixError{kind: FixErrorKind::InvalidFormat, field: tags::LastShares})?);
+      } else if tag == tags::Side {
+        side = Some(if value == "1" { 'B' } else { 'S' });
+      }
+    }
+    Ok(Fill{
+      symbol: symbol.ok_or(FixError{kind: FixErrorKind::MissingField, field: tags::Symbol})?,
+      clorid: clorid.ok_or(FixError{kind: FixErrorKind::MissingField, field: tags::ClOrdId})?,
+      exec_price: exec_price.ok_or(FixError{kind: FixErrorKind::MissingField, field: tags::LastPx})?,
+      exec_qty: exec_qty.ok_or(FixError{kind: FixErrorKind::MissingField, field: tags::LastShares})?,
+      side: side.ok_or(FixError{kind: FixErrorKind::MissingField, field: tags::Side})?,
+      aggr_ind: 'A',
+      orderid: None,
+      execid: None,
+      leaves_qty: None,
+    })
+  }
+
+  #[cfg(feature = "std")]
+  #[allow(clippy::too_many_arguments)]
+  pub fn serialize(sendercompid: &str, targetcompid: &str, seqno: u32, clordid: &str, orderid: &str, symbol: &str, execid: u64, exec_price: i32, exec_qty: i32, leaves_qty: i32, side: char) -> Vec<u8> {
+    let fill = Fill{
+      symbol, clorid: clordid, exec_price, exec_qty, side, aggr_ind: 'A',
+      orderid: Some(orderid), execid: Some(execid), leaves_qty: Some(leaves_qty),
+    };
+    encode(&fill, sendercompid, targetcompid, seqno)
+  }
+}
+impl<'a> FixDecode<'a> for Fill<'a> {
+  fn decode(fixmsg: &'a str) -> Result<Self, FixError> {
+    Fill::new(fixmsg.as_bytes())
+  }
+}
+impl<'a> FixEncode for Fill<'a> {
+  fn msg_type(&self) -> &'static str { "8" }
+  fn encode_body(&self, cur: &mut cursor::CursorMut<'_>) -> Result<(), FixError> {
+    let exec_price = ((self.exec_price as f64) / 10000.0).to_string();
+    let exec_qty = self.exec_qty.to_string();
+    let tipe = if self.leaves_qty == Some(0) { "2" } else { "1" };
+    cur.put_key_value(tags::ClOrdId, self.clorid);
+    if let Some(orderid) = self.orderid {
+      cur.put_key_value(tags::OrderID, orderid);
+    }
+    cur.put_key_value(tags::ExecTransType, "0");
+    cur.put_key_value(tags::OrdStatus, tipe);
+    cur.put_key_value(tags::ExecType, tipe);
+    cur.put_key_value(tags::Symbol, self.symbol);
+    if let Some(execid) = self.execid {
+      cur.put_key_value(tags::ExecID, &execid.to_string());
+    }
+    cur.put_key_value(tags::LastPx, &exec_price);
+    cur.put_key_value(tags::LastShares, &exec_qty);
+    Ok(())
   }
 }
 #[derive(Debug)]
@@ -190,83 +533,194 @@ pub struct Login {
   pub seqno: u32,
 }
 impl Login {
-  pub fn new(msg: &HashMap<i32, &str>) -> Self {
-    Self {
-      sendercompid: msg.get(&tags::SenderCompID).unwrap().to_string(),
-      targetcompid: msg.get(&tags::TargetCompID).unwrap().to_string(),
-      seqno: msg.get(&tags::MsgSeqNum).unwrap().parse().unwrap(),
-    }
+  pub fn new(msg: &FixMap<i32, &str>) -> Result<Self, FixError> {
+    check_required(msg, &msgdefs::LOGIN)?;
+    let sendercompid = get_or_fail(msg, tags::SenderCompID)?;
+    let targetcompid = get_or_fail(msg, tags::TargetCompID)?;
+    let seqno = get_or_fail(msg, tags::MsgSeqNum)?;
+    let seqno = seqno.parse().map_err(|_| FixError{kind: FixErrorKind::InvalidFormat, field: tags::MsgSeqNum})?;
+    Ok(Self { sendercompid, targetcompid, seqno })
   }
+  #[cfg(feature = "std")]
   pub fn serialize(sendercompid: &str, targetcompid: &str, seqno: u32) -> Vec<u8> {
-    serialize("A", sendercompid, targetcompid, seqno, &HashMap::new())
+    serialize("A", sendercompid, targetcompid, seqno, &[])
   }
 }
+impl<'a> FixDecode<'a> for Login {
+  fn decode(fixmsg: &'a str) -> Result<Self, FixError> {
+    Login::new(&to_fix_hash(fixmsg)?)
+  }
+}
+impl FixEncode for Login {
+  fn msg_type(&self) -> &'static str { "A" }
+  fn encode_body(&self, _cur: &mut cursor::CursorMut<'_>) -> Result<(), FixError> { Ok(()) }
+}
 #[derive(Debug)]
 pub struct Logout;
+impl<'a> FixDecode<'a> for Logout {
+  fn decode(_fixmsg: &'a str) -> Result<Self, FixError> {
+    Ok(Logout{})
+  }
+}
+impl FixEncode for Logout {
+  fn msg_type(&self) -> &'static str { "5" }
+  fn encode_body(&self, _cur: &mut cursor::CursorMut<'_>) -> Result<(), FixError> { Ok(()) }
+}
 #[derive(Debug)]
 pub struct Heartbeat;
 impl Heartbeat {
+  #[cfg(feature = "std")]
   pub fn serialize(sendercompid: &str, targetcompid: &str, seqno: u32) -> Vec<u8> {
-    serialize("0", sendercompid, targetcompid, seqno, &HashMap::new())
+    serialize("0", sendercompid, targetcompid, seqno, &[])
+  }
+}
+impl<'a> FixDecode<'a> for Heartbeat {
+  fn decode(fixmsg: &'a str) -> Result<Self, FixError> {
+    check_required(&to_fix_hash(fixmsg)?, &msgdefs::HEARTBEAT)?;
+    Ok(Heartbeat{})
+  }
+}
+impl FixEncode for Heartbeat {
+  fn msg_type(&self) -> &'static str { "0" }
+  fn encode_body(&self, _cur: &mut cursor::CursorMut<'_>) -> Result<(), FixError> { Ok(()) }
+}
+#[derive(Debug)]
+pub struct TestRequest {
+  pub test_req_id: String,
+}
+impl TestRequest {
+  pub fn new(m: &FixMap<i32, &str>) -> Result<TestRequest, FixError> {
+    check_required(m, &msgdefs::TESTREQUEST)?;
+    let test_req_id = get_or_fail(m, tags::TestReqID)?;
+    Ok(TestRequest{test_req_id})
+  }
+}
+impl<'a> FixDecode<'a> for TestRequest {
+  fn decode(fixmsg: &'a str) -> Result<Self, FixError> {
+    TestRequest::new(&to_fix_hash(fixmsg)?)
+  }
+}
+impl FixEncode for TestRequest {
+  fn msg_type(&self) -> &'static str { "1" }
+  fn encode_body(&self, cur: &mut cursor::CursorMut<'_>) -> Result<(), FixError> {
+    cur.put_key_value(tags::TestReqID, &self.test_req_id);
+    Ok(())
   }
 }
 #[derive(Debug)]
 pub struct ResendRequest {
+  pub begin_seqno: u32,
   pub end_seqno: u32,
 }
 impl ResendRequest {
-  pub fn new(m: &HashMap<i32, &str>) -> Result<ResendRequest, FixError> {
+  pub fn new(m: &FixMap<i32, &str>) -> Result<ResendRequest, FixError> {
+    check_required(m, &msgdefs::RESENDREQUEST)?;
+    let begin_seqno = m.get(&tags::BeginSeqNo).ok_or(FixError{kind:FixErrorKind::MissingField, field:tags::BeginSeqNo})?;
+    let begin_seqno = begin_seqno.parse().map_err(|_| FixError{kind: FixErrorKind::InvalidFormat, field: tags::BeginSeqNo})?;
     let end_seqno = m.get(&tags::EndSeqNo).ok_or(FixError{kind:FixErrorKind::MissingField, field:tags::EndSeqNo})?;
     let end_seqno = end_seqno.parse().map_err(|_| FixError{kind: FixErrorKind::InvalidFormat, field: tags::EndSeqNo})?;
-    Ok(ResendRequest{end_seqno: end_seqno})
+    Ok(ResendRequest{begin_seqno, end_seqno})
+  }
+}
+impl<'a> FixDecode<'a> for ResendRequest {
+  fn decode(fixmsg: &'a str) -> Result<Self, FixError> {
+    ResendRequest::new(&to_fix_hash(fixmsg)?)
+  }
+}
+impl FixEncode for ResendRequest {
+  fn msg_type(&self) -> &'static str { "2" }
+  fn encode_body(&self, cur: &mut cursor::CursorMut<'_>) -> Result<(), FixError> {
+    cur.put_key_value(tags::BeginSeqNo, &self.begin_seqno.to_string());
+    cur.put_key_value(tags::EndSeqNo, &self.end_seqno.to_string());
+    Ok(())
   }
 }
 #[derive(Debug)]
 pub struct SequenceReset;
 impl SequenceReset {
+  #[cfg(feature = "std")]
   pub fn serialize(sendercompid: &str, targetcompid: &str, seqno: u32, new_seqno: u32, gap_fill: bool) -> Vec<u8> {
     let new_seqno = new_seqno.to_string();
-    let fields : HashMap<i32, &str> = vec![(tags::GapFillFlag, if gap_fill { "Y" } else { "N" }), (tags::NewSeqNo, &new_seqno)].into_iter().collect();
+    let fields : Vec<(i32, &str)> = vec![(tags::GapFillFlag, if gap_fill { "Y" } else { "N" }), (tags::NewSeqNo, &new_seqno)];
     serialize("4", sendercompid, targetcompid, seqno, &fields)
   }
 }
+impl<'a> FixDecode<'a> for SequenceReset {
+  fn decode(fixmsg: &'a str) -> Result<Self, FixError> {
+    check_required(&to_fix_hash(fixmsg)?, &msgdefs::SEQUENCERESET)?;
+    Ok(SequenceReset{})
+  }
+}
 
-fn serialize_body<'a>(msg: &HashMap<i32, &str>, buf: &'a mut [u8]) -> &'a [u8]{
-  let mut cursor = Cursor::new(buf);
-  for (k,v) in msg.iter()
-    .filter(|&(k,_)| !vec![tags::BeginString, tags::BodyLength, tags::MsgType, tags::MsgSeqNum].contains(k)) {
-      write!(cursor, "{}={}\x01", k,v).expect("can't write!()");
+fn serialize_body<'a>(msg: &[(i32, &str)], buf: &'a mut [u8]) -> &'a [u8]{
+  let mut cur = cursor::CursorMut::new(buf);
+  for &(k,v) in msg.iter()
+    .filter(|&&(k,_)| ![tags::BeginString, tags::BodyLength, tags::MsgType, tags::MsgSeqNum].contains(&k)) {
+      cur.put_key_value(k, v);
   }
-  let len = cursor.position() as usize;
-  return &cursor.into_inner()[..len];
+  cur.into_inner()
 }
 
-fn serialize_head<'a>(msg_type: &str, sendercompid: &str, targetcompid: &str, seqno: u32, body: &[u8], buf: &'a mut [u8]) -> &'a [u8] {
+fn serialize_head<'a>(msg_type: &str, sendercompid: &str, targetcompid: &str, seqno: u32, body: &[u8], buf: &'a mut [u8], clock: &impl Clock) -> &'a [u8] {
   let timestamp_format = "YYYYMMDD-HH:MM:SS.sss";
   let mut cursor = Cursor::new(buf);
   let msg_len = 4 + msg_type.len()
-              + 4 + sendercompid.len() 
+              + 4 + sendercompid.len()
               + 4 + targetcompid.len()
               + 4 + timestamp_format.len()
               + 4 + 7 // seqno
               + body.len();
-  let dt = Utc::now();
-  let dtstr = dt.format("%Y%m%d-%T%.3f");
-  write!(cursor, "8=FIX4.2\x019={}\x0135={}\x0152={}\x0149={}\x0156={}\x0134={:07}\x01", msg_len, msg_type, dtstr, sendercompid, targetcompid, seqno).unwrap();
+  let mut time_buf = [0_u8; 32];
+  let sending_time = clock.sending_time(&mut time_buf);
+  write!(cursor, "8=FIX4.2\x019={}\x0135={}\x0152={}\x0149={}\x0156={}\x0134={:07}\x01", msg_len, msg_type, sending_time, sendercompid, targetcompid, seqno).unwrap();
   let len = cursor.position() as usize;
-  return &cursor.into_inner()[..len];
+  &cursor.into_inner()[..len]
 }
 
-fn serialize<'a>(msg_type: &str, sendercompid: &str, targetcompid: &str, seqno: u32, msg: &HashMap<i32, &str>) -> Vec<u8> {
-  let mut body_buf = [0 as u8; 1024];
-  let mut head_buf = [0 as u8; 1024];
-  let body = serialize_body(&msg, &mut body_buf[..]);
-  let head = serialize_head(msg_type, sendercompid, targetcompid, seqno, body, &mut head_buf[..]);
-  let mut tail_buf = [0 as u8; 8];
-  write!(&mut tail_buf[..], "10={:03}\x01", get_checksum(body, head)).unwrap();
+/// Builds `msg_type`'s wire bytes out of a raw field list, stamping
+/// `SendingTime` via `clock`. The `std`-feature convenience wrapper
+/// `serialize` below calls this with `ChronoClock`; `no_std` callers without
+/// `chrono` call it directly with their own `Clock`.
+pub fn serialize_with_clock(msg_type: &str, sendercompid: &str, targetcompid: &str, seqno: u32, msg: &[(i32, &str)], clock: &impl Clock) -> Vec<u8> {
+  let mut body_buf = [0_u8; 1024];
+  let mut head_buf = [0_u8; 1024];
+  let body = serialize_body(msg, &mut body_buf[..]);
+  let head = serialize_head(msg_type, sendercompid, targetcompid, seqno, body, &mut head_buf[..], clock);
+  let mut tail_buf = [0_u8; 8];
+  let mut tail = Cursor::new(&mut tail_buf[..]);
+  write!(tail, "10={:03}\x01", get_checksum(body, head)).unwrap();
   [head, body, &tail_buf[..7]].concat()
 }
 
+#[cfg(feature = "std")]
+fn serialize(msg_type: &str, sendercompid: &str, targetcompid: &str, seqno: u32, msg: &[(i32, &str)]) -> Vec<u8> {
+  serialize_with_clock(msg_type, sendercompid, targetcompid, seqno, msg, &ChronoClock)
+}
+
+/// Serializes any `FixEncode` message, driving its `encode_body` and `clock`
+/// instead of taking a hand-built field list like `serialize_with_clock`.
+pub fn encode_with_clock(msg: &impl FixEncode, sendercompid: &str, targetcompid: &str, seqno: u32, clock: &impl Clock) -> Vec<u8> {
+  let mut body_buf = [0_u8; 1024];
+  let body = {
+    let mut cur = cursor::CursorMut::new(&mut body_buf[..]);
+    msg.encode_body(&mut cur).expect("encode_body failed");
+    cur.into_inner()
+  };
+  let mut head_buf = [0_u8; 1024];
+  let head = serialize_head(msg.msg_type(), sendercompid, targetcompid, seqno, body, &mut head_buf[..], clock);
+  let mut tail_buf = [0_u8; 8];
+  let mut tail = Cursor::new(&mut tail_buf[..]);
+  write!(tail, "10={:03}\x01", get_checksum(body, head)).unwrap();
+  [head, body, &tail_buf[..7]].concat()
+}
+
+/// `encode_with_clock` stamped with `ChronoClock`, for `std` callers that
+/// don't need to supply their own `SendingTime` source.
+#[cfg(feature = "std")]
+pub fn encode(msg: &impl FixEncode, sendercompid: &str, targetcompid: &str, seqno: u32) -> Vec<u8> {
+  encode_with_clock(msg, sendercompid, targetcompid, seqno, &ChronoClock)
+}
+
 fn get_checksum(header: &[u8], body: &[u8]) -> u8 {
   let mut checksum : usize = 0;
   // TODO sfortas vectorize
@@ -276,18 +730,224 @@ fn get_checksum(header: &[u8], body: &[u8]) -> u8 {
   for byte in body {
     checksum += *byte as usize;
   }
-  return (checksum & 0xff) as u8;
+  (checksum & 0xff) as u8
+}
+
+/// Session-layer engine: sequence-number bookkeeping, resend/gap-fill, and
+/// heartbeat/test-request timers on top of the message (de)serialization
+/// above. This is the missing glue that turns the parsers into an actual
+/// FIX client.
+///
+/// Requires `std`: the transport bound (`std::io::Write`) and heartbeat
+/// timer (`std::time::Instant`) have no `alloc`-only equivalent, so this
+/// module isn't available to `no_std` callers.
+#[cfg(feature = "std")]
+pub mod session {
+  use std::io::Write;
+  use std::time::{Duration, Instant};
+  use super::{tags, serialize, SequenceReset, FixError, FixErrorKind, ResendRequest, TestRequest};
+
+  /// A blocking FIX client: every call stamps sequence numbers and blocks
+  /// the calling thread until the bytes are on the wire. Inbound gaps are
+  /// answered with a `ResendRequest` automatically; a peer's
+  /// `ResendRequest` is answered through `Session::handle_resend_request`,
+  /// and heartbeat/`TestRequest` timing runs when the caller calls
+  /// `Session::poll_heartbeat`/`Session::poll_test_request`. There is no
+  /// non-blocking counterpart.
+  pub trait SyncClient {
+    fn send(&mut self, msg_type: &str, fields: &[(i32, &str)]) -> Result<(), FixError>;
+  }
+
+  /// Tracks inbound/outbound `MsgSeqNum`s and the sent-message store needed
+  /// to answer a peer's `ResendRequest`, and drives heartbeat/`TestRequest`
+  /// timing. `check_inbound_seqno` issues the `ResendRequest` itself as soon
+  /// as it sees a gap, and `poll_heartbeat`/`poll_test_request` (called from
+  /// the caller's event loop/timer tick) send a heartbeat or a `TestRequest` once the
+  /// corresponding side of the connection has been quiet for
+  /// `heartbeat_interval`.
+  pub struct Session<W: Write> {
+    pub sendercompid: String,
+    pub targetcompid: String,
+    transport: W,
+    next_out_seqno: u32,
+    next_in_seqno: u32,
+    sent: Vec<Vec<u8>>, // sent[i] holds the message with MsgSeqNum i + 1
+    heartbeat_interval: Duration,
+    last_sent_at: Instant,
+    last_received_at: Instant,
+    test_request_pending: bool,
+    test_req_counter: u32,
+    // `Some(end)` while a `ResendRequest` covering `next_in_seqno..=end` is
+    // outstanding, so a burst of further out-of-order messages before the
+    // peer's resend arrives doesn't trigger a fresh request each time.
+    pending_resend_end: Option<u32>,
+  }
+
+  impl<W: Write> Session<W> {
+    pub fn new(sendercompid: &str, targetcompid: &str, heartbeat_interval: Duration, transport: W) -> Self {
+      let now = Instant::now();
+      Session {
+        sendercompid: sendercompid.to_string(),
+        targetcompid: targetcompid.to_string(),
+        transport,
+        next_out_seqno: 1,
+        next_in_seqno: 1,
+        sent: Vec::new(),
+        heartbeat_interval,
+        last_sent_at: now,
+        last_received_at: now,
+        test_request_pending: false,
+        test_req_counter: 0,
+        pending_resend_end: None,
+      }
+    }
+
+    pub fn next_out_seqno(&self) -> u32 {
+      self.next_out_seqno
+    }
+
+    /// Checks an inbound `MsgSeqNum` against the next one we expect and
+    /// resets the `TestRequest` timer, since hearing anything from the peer
+    /// (in order, a dup, or a gap) counts as a sign of life. `Ok(())` means
+    /// it was in order (or a dup to ignore); on a gap this fires off a
+    /// `ResendRequest` for the missing range and returns that `(begin, end)`
+    /// range to the caller for logging/diagnostics.
+    ///
+    /// `next_in_seqno` is deliberately left unmoved on a gap: the peer still
+    /// owes us `begin..=end`, and those seqnos must land in the `seqno ==
+    /// next_in_seqno` branch below (and advance it one at a time) when the
+    /// resend actually arrives. Fast-forwarding past the gap here would make
+    /// the resent messages look like dupes and drop them silently.
+    ///
+    /// Only one `ResendRequest` is issued per outstanding gap: further
+    /// out-of-order messages that arrive before the peer's resend land
+    /// within the range already requested and are reported back without
+    /// sending another one. A gap that widens past the requested range (a
+    /// later message arrives even further ahead) does trigger a fresh,
+    /// wider request.
+    pub fn check_inbound_seqno(&mut self, seqno: u32) -> Result<(), (u32, u32)> {
+      self.last_received_at = Instant::now();
+      self.test_request_pending = false;
+      if seqno < self.next_in_seqno {
+        return Ok(()); // already processed; caller should drop it.
+      }
+      if seqno > self.next_in_seqno {
+        let gap = (self.next_in_seqno, seqno - 1);
+        let already_requested = self.pending_resend_end.is_some_and(|end| gap.1 <= end);
+        if !already_requested {
+          let _ = self.send_resend_request(gap.0, gap.1); // best-effort; caller still learns the gap below
+          self.pending_resend_end = Some(gap.1);
+        }
+        return Err(gap);
+      }
+      self.next_in_seqno += 1;
+      if self.pending_resend_end.is_some_and(|end| self.next_in_seqno > end) {
+        self.pending_resend_end = None;
+      }
+      Ok(())
+    }
+
+    /// Answers a peer's `TestRequest` with a `Heartbeat` echoing its
+    /// `TestReqID`, per the FIX convention the peer relies on to confirm
+    /// we're still alive before it gives up on the connection.
+    pub fn handle_test_request(&mut self, tr: &TestRequest) -> Result<(), FixError> {
+      self.send("0", &[(tags::TestReqID, &tr.test_req_id)])
+    }
+
+    /// Replays the outbound messages covering `rr.begin_seqno..=rr.end_seqno`
+    /// (an `end_seqno` of `0` means "through the current sequence number",
+    /// per the FIX `ResendRequest` convention) in response to `rr`, falling
+    /// back to a `SequenceReset`/gap-fill for anything no longer in the
+    /// store.
+    pub fn handle_resend_request(&mut self, rr: &ResendRequest) -> Result<(), FixError> {
+      let end_seqno = if rr.end_seqno == 0 { self.next_out_seqno.saturating_sub(1) } else { rr.end_seqno };
+      let mut seqno = rr.begin_seqno;
+      while seqno <= end_seqno {
+        match self.sent.get((seqno - 1) as usize) {
+          Some(buf) => {
+            let buf = buf.clone();
+            self.write_raw(&buf)?;
+            seqno += 1;
+          },
+          None => {
+            let gapfill = SequenceReset::serialize(&self.sendercompid, &self.targetcompid, seqno, seqno + 1, true);
+            self.write_raw(&gapfill)?;
+            seqno += 1;
+          },
+        }
+      }
+      Ok(())
+    }
+
+    pub fn send_heartbeat(&mut self) -> Result<(), FixError> {
+      self.send("0", &[])
+    }
+
+    pub fn send_resend_request(&mut self, begin_seqno: u32, end_seqno: u32) -> Result<(), FixError> {
+      let begin_seqno = begin_seqno.to_string();
+      let end_seqno = end_seqno.to_string();
+      self.send("2", &[(tags::BeginSeqNo, &begin_seqno), (tags::EndSeqNo, &end_seqno)])
+    }
+
+    pub fn send_test_request(&mut self, test_req_id: &str) -> Result<(), FixError> {
+      self.send("1", &[(tags::TestReqID, test_req_id)])
+    }
+
+    /// Sends a heartbeat if nothing has gone out since `heartbeat_interval`
+    /// elapsed. Call this from the caller's event loop/timer tick.
+    pub fn poll_heartbeat(&mut self) -> Result<(), FixError> {
+      if self.last_sent_at.elapsed() >= self.heartbeat_interval {
+        self.send_heartbeat()?;
+      }
+      Ok(())
+    }
+
+    /// Sends a `TestRequest` if nothing has come in from the peer since
+    /// `heartbeat_interval` elapsed, per the FIX convention of proving
+    /// liveness before giving up on the connection. Only one `TestRequest`
+    /// is outstanding at a time; `check_inbound_seqno` clears the pending
+    /// flag as soon as anything arrives. Call this alongside
+    /// `poll_heartbeat` from the caller's event loop/timer tick.
+    pub fn poll_test_request(&mut self) -> Result<(), FixError> {
+      if !self.test_request_pending && self.last_received_at.elapsed() >= self.heartbeat_interval {
+        self.test_req_counter += 1;
+        let test_req_id = self.test_req_counter.to_string();
+        self.send_test_request(&test_req_id)?;
+        self.test_request_pending = true;
+      }
+      Ok(())
+    }
+
+    fn write_raw(&mut self, buf: &[u8]) -> Result<(), FixError> {
+      self.last_sent_at = Instant::now();
+      self.transport.write_all(buf).map_err(|_| FixError{ kind: FixErrorKind::Parse, field: 0 })
+    }
+  }
+
+  impl<W: Write> SyncClient for Session<W> {
+    /// Stamps `MsgSeqNum`/`SenderCompID`/`TargetCompID`, stores the message
+    /// so it can be replayed on a `ResendRequest`, and writes it to the
+    /// transport.
+    fn send(&mut self, msg_type: &str, fields: &[(i32, &str)]) -> Result<(), FixError> {
+      let seqno = self.next_out_seqno;
+      let buf = serialize(msg_type, &self.sendercompid, &self.targetcompid, seqno, fields);
+      self.sent.push(buf.clone());
+      self.next_out_seqno += 1;
+      self.write_raw(&buf)
+    }
+  }
 }
 
 #[derive(Debug)]
-pub enum Message {
+pub enum Message<'a> {
   Login(Login),
   Heartbeat(Heartbeat),
-  New(NewOrder),
+  TestRequest(TestRequest),
+  New(NewOrder<'a>),
   Cancel(CancelOrder),
   NewAck(NewOrderAck),
   CancelAck(CancelOrderAck),
-  Fill(Fill),
+  Fill(Fill<'a>),
   Logout(Logout),
   ResendRequest(ResendRequest),
   SequenceReset(SequenceReset),
@@ -295,86 +955,126 @@ pub enum Message {
 
 static FIX_SEPARATOR : &str = "\x01"; 
 
-/// Parses a FIX string into a hashmap<fieldno, value>.
+/// Parses a FIX string into a hashmap<fieldno, value>, failing on a
+/// malformed `tag=value` token (no `=`, or a non-numeric tag) instead of
+/// panicking — callers see this before any trailer/required-field checks,
+/// so it's the first line of defense against a corrupted message.
 ///
 /// # Example
 ///
 /// ```
 /// use fix::to_fix_hash;
 /// let fix_string = "8=FIX4.2\x0135=A\x0134=1234\x0149=FOOBAR\x0156=BAZQUX\x0110=000\x01";
-/// let fix_msg = to_fix_hash(&fix_string);
+/// let fix_msg = to_fix_hash(&fix_string).unwrap();
 /// assert_eq!(fix_msg.get(&35), Some(&"A"));
 /// ```
-pub fn to_fix_hash(string: &str) -> HashMap<i32, &str> {
+pub fn to_fix_hash(string: &str) -> Result<FixMap<i32, &str>, FixError> {
     string.split(FIX_SEPARATOR)
-        .filter(|s| s.len() > 0)
-        .map(|s| s.split_at(s.find("=").unwrap()))
-        .map(|(key, val)| (key.parse().unwrap(), &val[1..]))
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+          let eq = s.find('=').ok_or(FixError{kind: FixErrorKind::InvalidFormat, field: 0})?;
+          let (key, val) = s.split_at(eq);
+          let key : i32 = key.parse().map_err(|_| FixError{kind: FixErrorKind::InvalidFormat, field: 0})?;
+          Ok((key, &val[1..]))
+        })
         .collect()
-    // let mut map = HashMap::new();
-    // let sp = s.split("|");
-    // for (i, tok) in sp.enumerate() {
-    //     println!("{}: {}", i, tok);
-    //     if tok.len() == 0 {
-    //         continue;
-    //     }
-    //     let kv : Vec<&str> = tok.split("=").collect();
-    //     println!("kv.len() = {}", kv.len());
-    //     for item in &kv {
-    //         println!("item: {}", item);
-    //     }
-    //     // assert_eq!(kv.len(), 2);
-    //     map.insert(kv[0].parse().expect("not an integer"), kv[1]);
-    // }
-    // return map;
-}
-
-/// Parses a FIX string into a Result<fix::Message, fix::FixError>
+}
+
+/// Recomputes tag 10 (CheckSum) and tag 9 (BodyLength) from the raw bytes of
+/// `fixmsg` and compares them to the values the message itself claims.
+///
+/// `index` is the position of the `\x01` immediately preceding `10=`, as
+/// already located by the caller. CheckSum is the sum, mod 256, of every
+/// byte from the start of the message through and including that `\x01`;
+/// BodyLength is the count of bytes from just after the `9=...\x01` field
+/// through and including that same `\x01`.
+fn verify_trailer(fixmsg: &str, index: usize, hash: &FixMap<i32, &str>) -> Result<(), FixError> {
+  let checksum_region = &fixmsg.as_bytes()[..index + 1];
+  let computed_checksum = checksum_region.iter().fold(0usize, |acc, &b| acc + b as usize) & 0xff;
+  let checksum = get_or_fail(hash, tags::CheckSum)?;
+  let checksum : usize = checksum.parse().map_err(|_| FixError{kind: FixErrorKind::InvalidFormat, field: tags::CheckSum})?;
+  if computed_checksum != checksum {
+    return Err(FixError{kind: FixErrorKind::BadCheckSum, field: tags::CheckSum});
+  }
+
+  let nine_pos = fixmsg.find("\x019=")
+    .ok_or(FixError{kind: FixErrorKind::MissingField, field: tags::BodyLength})?;
+  let after_nine = &fixmsg[nine_pos + 1..];
+  let nine_value_end = after_nine.find('\x01')
+    .ok_or(FixError{kind: FixErrorKind::Incomplete, field: tags::BodyLength})?;
+  let body_start = nine_pos + 1 + nine_value_end + 1;
+  let actual_body_len = index + 1 - body_start;
+  let bodylength = get_or_fail(hash, tags::BodyLength)?;
+  let bodylength : usize = bodylength.parse().map_err(|_| FixError{kind: FixErrorKind::InvalidFormat, field: tags::BodyLength})?;
+  if actual_body_len != bodylength {
+    return Err(FixError{kind: FixErrorKind::BadBodyLength, field: tags::BodyLength});
+  }
+  Ok(())
+}
+
+/// Parses a FIX string into a Result<fix::Message, fix::FixError>, validating
+/// the CheckSum (tag 10) and BodyLength (tag 9) trailer fields. Use
+/// `parse_unchecked` to skip that validation.
 ///
 /// # Example
 ///
 /// ```
 /// use fix::parse;
 /// use fix::Message;
-/// let fix_string = "8=FIX4.2\x0135=A\x0134=1234\x0149=BAZQUX\x0156=FOOBAR\x0110=000\x01";
+/// let fix_string = "8=FIX4.2\x019=33\x0135=A\x0134=1234\x0149=BAZQUX\x0156=FOOBAR\x0110=012\x01";
 /// let (bytes_eaten, login) = parse(&fix_string).unwrap();
 /// assert!(matches!(login, Message::Login{..}));
 /// ```
-pub fn parse(fixstr: &str ) -> Result<(usize, Message), FixError>  {
+pub fn parse<'a>(fixstr: &'a str ) -> Result<(usize, Message<'a>), FixError>  {
+  parse_impl(fixstr, true)
+}
+
+/// Parses a FIX string exactly like `parse`, but skips CheckSum/BodyLength
+/// validation, for callers who already trust the source (e.g. re-parsing a
+/// message this process just serialized, or replaying logged test data that
+/// predates this check).
+pub fn parse_unchecked<'a>(fixstr: &'a str ) -> Result<(usize, Message<'a>), FixError>  {
+  parse_impl(fixstr, false)
+}
+
+fn parse_impl<'a>(fixstr: &'a str, validate: bool) -> Result<(usize, Message<'a>), FixError>  {
   if let Some(index) = fixstr.find("\x0110=") {
     if fixstr.len() < index + 8 {
       return Err(FixError{kind: FixErrorKind::Incomplete, field:tags::CheckSum});
     }
     let fixmsg = &fixstr[..index+8];
     let bytes_eaten = index + 8;
-    let hash = to_fix_hash(fixmsg); // HashMap<i32, &str>
+    let hash = to_fix_hash(fixmsg)?; // FixMap<i32, &str>
+    if validate {
+      verify_trailer(fixmsg, index, &hash)?;
+    }
     if let Some(&msg_type) = hash.get(&tags::MsgType) {
       if msg_type == "A" {
-        return Ok((bytes_eaten, Message::Login(Login::new(&hash))));
+        Ok((bytes_eaten, Message::Login(Login::decode(fixmsg)?)))
       } else if msg_type == "5" {
-        return Ok((bytes_eaten, Message::Logout(Logout{})));
+        Ok((bytes_eaten, Message::Logout(Logout::decode(fixmsg)?)))
       } else if msg_type == "0" {
-        return Ok((bytes_eaten, Message::Heartbeat(Heartbeat{})));
+        Ok((bytes_eaten, Message::Heartbeat(Heartbeat::decode(fixmsg)?)))
+      } else if msg_type == "1" {
+        Ok((bytes_eaten, Message::TestRequest(TestRequest::decode(fixmsg)?)))
       } else if msg_type == "2" {
-        let rr = ResendRequest::new(&hash)?;
-        return Ok((bytes_eaten, Message::ResendRequest(rr)));
+        Ok((bytes_eaten, Message::ResendRequest(ResendRequest::decode(fixmsg)?)))
       } else if msg_type == "4" {
-        return Ok((bytes_eaten, Message::SequenceReset(SequenceReset{})));
+        Ok((bytes_eaten, Message::SequenceReset(SequenceReset::decode(fixmsg)?)))
       } else if msg_type == "D" {
-        let obj = NewOrder::new(&hash)?;
-        return Ok((bytes_eaten, Message::New(obj)));
+        let obj = NewOrder::decode(fixmsg)?;
+        Ok((bytes_eaten, Message::New(obj)))
       } else if msg_type == "F" {
-        println!("Cancel {:?}!", hash);
-        let obj = CancelOrder::new(&hash)?;
-        return Ok((bytes_eaten, Message::Cancel(obj)));
+        let obj = CancelOrder::decode(fixmsg)?;
+        Ok((bytes_eaten, Message::Cancel(obj)))
       } else if msg_type == "8" {
         // return Err(FixError{kind:FixErrorKind::Parse, field:0});
         if let Some(&ord_status) = hash.get(&tags::ExecType) {
           if ord_status == "0" {
-            let obj = NewOrderAck::new(&hash)?;
+            let obj = NewOrderAck::decode(fixmsg)?;
             return Ok((bytes_eaten, Message::NewAck(obj)));
           } else if ord_status == "1" || ord_status == "2" {
-            let obj = Fill::new(&hash)?;
+            let obj = Fill::decode(fixmsg)?;
             return Ok((bytes_eaten, Message::Fill(obj)));
           // } else if ord_status == "4" || ord_status == "C" {
           //   // canceled
@@ -383,23 +1083,24 @@ pub fn parse(fixstr: &str ) -> Result<(usize, Message), FixError>  {
         } else {
           return Err(FixError{kind: FixErrorKind::MissingField, field:tags::ExecType});
         }
-        return Err(FixError{kind: FixErrorKind::MissingField, field:tags::ExecType});
+        Err(FixError{kind: FixErrorKind::MissingField, field:tags::ExecType})
       } else {
-        return Err(FixError{kind: FixErrorKind::UnexpectedMessage, field:0});
+        Err(FixError{kind: FixErrorKind::UnexpectedMessage, field:0})
       }
     } else {
     Err(FixError{kind: FixErrorKind::MissingField, field:35})
     }
   } else {
-    return Err(FixError{kind: FixErrorKind::Incomplete, field:tags::CheckSum});
+    Err(FixError{kind: FixErrorKind::Incomplete, field:tags::CheckSum})
   }
 }
 
+#[cfg(feature = "std")]
 #[test]
 fn test_to_fix_hash() {
     let data = b"8=FIX4.2\x0135=D\x0155=AAPL\x0139=100\x0111=CLORDID1\x0144=134.56\x0159=SENDER\x0110=101\x01";
     let data = str::from_utf8(data).unwrap();
-    let fix = to_fix_hash(&data);
+    let fix = to_fix_hash(data).unwrap();
     for (key, value) in &fix {
         println!("{}: \"{}\"", key, value);
     }
@@ -407,11 +1108,12 @@ fn test_to_fix_hash() {
     assert_eq!(fix.get(&8), Some(&"FIX4.2"));
 }
 
+#[cfg(feature = "std")]
 #[test]
 fn test_parse_new_order() {
     let data = b"8=FIX4.2\x0135=D\x0155=AAPL\x0139=100\x0111=CLORDID1\x0154=2\x0144=134.56\x0138=600\x0159=SENDER\x0110=101\x01";
     let data = str::from_utf8(data).unwrap();
-    let out = parse(data);
+    let out = parse_unchecked(data);
     println!("{:?}", out);
     assert!(out.is_ok());
     let (_, msg) = out.unwrap();
@@ -426,66 +1128,113 @@ fn test_parse_new_order() {
     }
 }
 
+#[cfg(feature = "std")]
 #[test]
 fn test_parse_cancel() {
     let data = b"8=FIX4.2\x0135=F\x0155=AAPL\x0139=100\x0111=CXL-CLORDID1\x0141=CLORDID1\x0144=134.56\x0159=SENDER\x0110=101\x01";
     let bytes = data.len();
     let data = str::from_utf8(data).unwrap();
-    let out = parse(data);
+    let out = parse_unchecked(data);
     assert!(out.is_ok());
     println!("{:?}", out);
-    let is_cancel = |m| {
-      match m {
-        Message::Cancel{..} => true,
-        _ => false,
-      }
-    };
+    let is_cancel = |m| matches!(m, Message::Cancel{..});
     let (bytes_eaten, msg) = out.unwrap();
     assert_eq!(bytes_eaten, bytes);
     assert!(is_cancel(msg));
 }
 
+#[cfg(feature = "std")]
 #[test]
 fn test_parse_ack() {
-    let data = b"8=FIX4.2\x0135=8\x0155=AAPL\x01150=0\x0111=CLORDID1\x0144=134.56\x0159=SENDER\x0110=101\x01";
+    let data = b"8=FIX4.2\x0135=8\x0155=AAPL\x01150=0\x0111=CLORDID1\x0137=ORDERID1\x0138=100\x0154=1\x0144=134.56\x0159=SENDER\x0110=101\x01";
     let data = str::from_utf8(data).unwrap();
-    let out = parse(data);
+    let out = parse_unchecked(data);
     assert!(out.is_ok());
     println!("{:?}", out);
     let (_, msg) = out.unwrap();
     assert!(matches!(msg, Message::NewAck{..}));
 }
 
+#[cfg(feature = "std")]
 #[test]
 fn test_parse_fill() {
     let data = b"8=FIX4.2\x0135=8\x0155=AAPL\x01150=1\x0111=CLORDID1\x0131=134.55\x0132=300\x0154=1\x0144=134.56\x0159=SENDER\x0110=101\x01";
     let data = str::from_utf8(data).unwrap();
-    let out = parse(data);
+    let out = parse_unchecked(data);
     println!("{:?}", out);
     assert!(out.is_ok());
     let (_, msg) = out.unwrap();
     assert!(matches!(msg, Message::Fill{..}));
 }
 
+#[cfg(feature = "std")]
 #[test]
 fn test_parse_fill_fail() {
     let data = b"8=FIX4.2\x0135=8\x0155=AAPL\x01150=1\x0111=CLORDID1\x0131=134.55\x0132=ABCD\x0154=1\x0144=134.56\x0159=SENDER\x0110=101\x01";
     let data = str::from_utf8(data).unwrap();
-    let out = parse(data);
+    let out = parse_unchecked(data);
     println!("{:?}", out);
     assert!(out.is_err());
     let err = out.err().unwrap();
     assert_eq!(err.kind, FixErrorKind::InvalidFormat);
 }
 
+#[cfg(feature = "std")]
 #[test]
 fn test_parse_fail() {
   // no symbol
   let data = b"8=FIX4.2\x0135=D\x0139=100\x0111=CLORDID1\x0144=134.56\x0159=SENDER\x0110=101\x01";
   let data = str::from_utf8(data).unwrap();
+  let out = parse_unchecked(data);
+  assert!(out.is_err());
+  println!("{}", out.expect_err(""));
+}
+
+#[test]
+fn test_parse_validates_trailer() {
+  // BodyLength 5 is "35=0\x01"; the bytes from "8=" through the SOH before
+  // "10=" sum to 883, which is 115 mod 256.
+  let data = "8=FIX4.2\x019=5\x0135=0\x0110=115\x01";
+  let out = parse(data);
+  assert!(out.is_ok());
+  let (_, msg) = out.unwrap();
+  assert!(matches!(msg, Message::Heartbeat{..}));
+}
+
+#[test]
+fn test_parse_bad_checksum() {
+  let data = "8=FIX4.2\x019=5\x0135=0\x0110=999\x01";
   let out = parse(data);
   assert!(out.is_err());
-  println!("{}", out.err().expect(""));
+  assert_eq!(out.err().unwrap().kind, FixErrorKind::BadCheckSum);
+}
+
+#[test]
+fn test_parse_bad_bodylength() {
+  let data = "8=FIX4.2\x019=4\x0135=0\x0110=114\x01";
+  let out = parse(data);
+  assert!(out.is_err());
+  assert_eq!(out.err().unwrap().kind, FixErrorKind::BadBodyLength);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_login_missing_sendercompid_is_fix_error_not_panic() {
+  // tag 49 (SenderCompID) is missing.
+  let data = "56=TARGET\x0134=1\x01";
+  let out = Login::decode(data);
+  assert!(out.is_err());
+  assert_eq!(out.err().unwrap().kind, FixErrorKind::MissingField);
+}
+
+#[test]
+fn test_parse_malformed_field_is_fix_error_not_panic() {
+  // "BADTOKEN" has no '=' separator; passes the CheckSum/BodyLength check,
+  // so it must be rejected by to_fix_hash instead of panicking.
+  let data = "8=FIX4.2\x019=14\x0135=A\x01BADTOKEN\x0110=253\x01";
+  let out = parse(data);
+  assert!(out.is_err());
+  assert_eq!(out.err().unwrap().kind, FixErrorKind::InvalidFormat);
 }
 
 #[test]
@@ -496,22 +1245,254 @@ fn test_atoi() {
     assert_eq!(the_number, 12345);
 }
 
+#[test]
+fn test_cursor_get_key_value_missing_equals() {
+  let buf = b"55AAPL\x01";
+  let mut cur = cursor::Cursor::new(buf);
+  let err = cur.get_key_value().expect_err("missing '=' should fail");
+  assert_eq!(err.kind, FixErrorKind::InvalidFormat);
+}
+
+#[test]
+fn test_cursor_get_key_value_missing_trailing_soh() {
+  let buf = b"55=AAPL";
+  let mut cur = cursor::Cursor::new(buf);
+  let err = cur.get_key_value().expect_err("missing trailing SOH should fail");
+  assert_eq!(err.kind, FixErrorKind::Incomplete);
+}
+
+#[test]
+fn test_cursor_get_key_value_non_numeric_tag() {
+  let buf = b"AA=AAPL\x01";
+  let mut cur = cursor::Cursor::new(buf);
+  let err = cur.get_key_value().expect_err("non-numeric tag should fail");
+  assert_eq!(err.kind, FixErrorKind::InvalidFormat);
+}
+
+#[test]
+fn test_cursor_mut_put_key_value() {
+  let mut buf = [0_u8; 32];
+  let mut cur = cursor::CursorMut::new(&mut buf[..]);
+  cur.put_key_value(tags::Symbol, "AAPL");
+  let out = cur.into_inner();
+  assert_eq!(str::from_utf8(out).unwrap(), "55=AAPL\x01");
+}
+
 #[test]
 fn test_serialize_body() {
-  let msg : HashMap<i32, &str> = vec![(8,"FIX4.2"),(9,"1234"),(52,"BAH"),(54,"QUX"),(99,"FOOBAR")].into_iter().collect();
-  let mut body_buf = [0 as u8; 1024];
+  let msg : Vec<(i32, &str)> = vec![(8,"FIX4.2"),(9,"1234"),(52,"BAH"),(54,"QUX"),(99,"FOOBAR")];
+  let mut body_buf = [0_u8; 1024];
   let body = serialize_body(&msg, &mut body_buf[..]);
-  let body_str =str::from_utf8(&body).unwrap();
+  let body_str = str::from_utf8(body).unwrap();
   assert!(body_str.contains("52=BAH\x01") &&
           body_str.contains("54=QUX\x01") &&
           body_str.contains("99=FOOBAR\x01"));
 }
 
+/// A `Write` transport that also hands the test a handle on everything
+/// written, since `Session` owns its transport and never exposes it back.
+#[cfg(all(test, feature = "std"))]
+#[derive(Clone)]
+struct RecordingTransport(std::rc::Rc<std::cell::RefCell<Vec<u8>>>);
+
+#[cfg(all(test, feature = "std"))]
+impl RecordingTransport {
+  fn new() -> Self {
+    RecordingTransport(std::rc::Rc::new(std::cell::RefCell::new(Vec::new())))
+  }
+
+  fn written(&self) -> String {
+    String::from_utf8(self.0.borrow().clone()).unwrap()
+  }
+}
+
+#[cfg(all(test, feature = "std"))]
+impl std::io::Write for RecordingTransport {
+  fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+    self.0.borrow_mut().extend_from_slice(buf);
+    Ok(buf.len())
+  }
+  fn flush(&mut self) -> std::io::Result<()> {
+    Ok(())
+  }
+}
+
+#[cfg(all(test, feature = "std"))]
+fn test_session(heartbeat_interval: std::time::Duration) -> (session::Session<RecordingTransport>, RecordingTransport) {
+  let transport = RecordingTransport::new();
+  let sess = session::Session::new("SENDER", "TARGET", heartbeat_interval, transport.clone());
+  (sess, transport)
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_session_check_inbound_seqno_in_order() {
+  let (mut sess, _transport) = test_session(std::time::Duration::from_secs(30));
+  assert!(sess.check_inbound_seqno(1).is_ok());
+  assert!(sess.check_inbound_seqno(2).is_ok());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_session_check_inbound_seqno_dup_is_ignored() {
+  let (mut sess, _transport) = test_session(std::time::Duration::from_secs(30));
+  assert!(sess.check_inbound_seqno(1).is_ok());
+  // A re-delivered seqno 1 should be silently dropped, not treated as a gap.
+  assert!(sess.check_inbound_seqno(1).is_ok());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_session_check_inbound_seqno_gap_does_not_fastforward() {
+  let (mut sess, transport) = test_session(std::time::Duration::from_secs(30));
+  let err = sess.check_inbound_seqno(3).expect_err("a gap should be reported");
+  assert_eq!(err, (1, 2));
+  // The gap must not fast-forward next_in_seqno: the resend that fills
+  // seqno 1 still has to land in the in-order branch, not be mistaken for
+  // a dup of the (never-seen) seqno 3.
+  assert!(sess.check_inbound_seqno(1).is_ok());
+  assert!(sess.check_inbound_seqno(2).is_ok());
+  assert!(sess.check_inbound_seqno(3).is_ok());
+  // check_inbound_seqno should have auto-issued a ResendRequest for the gap.
+  assert!(transport.written().contains("35=2\x01"));
+  assert!(transport.written().contains(&format!("{}=1\x01", tags::BeginSeqNo)));
+  assert!(transport.written().contains(&format!("{}=2\x01", tags::EndSeqNo)));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_session_check_inbound_seqno_does_not_duplicate_resend_request() {
+  let (mut sess, transport) = test_session(std::time::Duration::from_secs(30));
+  assert_eq!(sess.check_inbound_seqno(3), Err((1, 2)));
+  let after_first = transport.written().matches("35=2\x01").count();
+  assert_eq!(after_first, 1);
+  // Further out-of-order arrivals within the same outstanding gap must not
+  // trigger another ResendRequest.
+  assert_eq!(sess.check_inbound_seqno(3), Err((1, 2)));
+  assert_eq!(transport.written().matches("35=2\x01").count(), after_first);
+  // A gap that widens past what was already requested does get a fresh,
+  // wider request.
+  assert_eq!(sess.check_inbound_seqno(6), Err((1, 5)));
+  assert_eq!(transport.written().matches("35=2\x01").count(), after_first + 1);
+  assert!(transport.written().contains(&format!("{}=5\x01", tags::EndSeqNo)));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_session_send_resend_request_carries_begin_and_end_seqno() {
+  let (mut sess, transport) = test_session(std::time::Duration::from_secs(30));
+  sess.send_resend_request(5, 9).unwrap();
+  let out = transport.written();
+  assert!(out.contains(&format!("{}=5\x01", tags::BeginSeqNo)));
+  assert!(out.contains(&format!("{}=9\x01", tags::EndSeqNo)));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_session_handle_resend_request_replays_sent_messages() {
+  use session::SyncClient;
+  let (mut sess, transport) = test_session(std::time::Duration::from_secs(30));
+  sess.send("0", &[]).unwrap();
+  sess.send("0", &[]).unwrap();
+  let before = transport.written().len();
+  let rr = ResendRequest{begin_seqno: 1, end_seqno: 2};
+  sess.handle_resend_request(&rr).unwrap();
+  // The two heartbeats should have been replayed verbatim on top of
+  // whatever was already written.
+  let after = transport.written();
+  assert!(after.len() > before);
+  assert_eq!(after.matches("35=0\x01").count(), 4);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_session_poll_heartbeat_sends_once_interval_elapses() {
+  let (mut sess, transport) = test_session(std::time::Duration::new(0, 0));
+  sess.poll_heartbeat().unwrap();
+  assert!(transport.written().contains("35=0\x01"));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_session_poll_test_request_sends_once_then_waits_for_reply() {
+  let (mut sess, transport) = test_session(std::time::Duration::new(0, 0));
+  sess.poll_test_request().unwrap();
+  assert!(transport.written().contains("35=1\x01"));
+  let after_first = transport.written().len();
+  // A second poll before the peer replies shouldn't send another TestRequest.
+  sess.poll_test_request().unwrap();
+  assert_eq!(transport.written().len(), after_first);
+  // Once the peer is heard from again, the pending flag clears and a new
+  // TestRequest can go out.
+  sess.check_inbound_seqno(1).unwrap();
+  sess.poll_test_request().unwrap();
+  assert!(transport.written().len() > after_first);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_parse_test_request() {
+  let data = "8=FIX4.2\x019=12\x0135=1\x01112=7\x0110=067\x01";
+  let out = parse_unchecked(data);
+  assert!(out.is_ok());
+  let (_, msg) = out.unwrap();
+  match msg {
+    Message::TestRequest(tr) => assert_eq!(tr.test_req_id, "7"),
+    _ => panic!("expected TestRequest"),
+  }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_session_handle_test_request_echoes_test_req_id() {
+  let (mut sess, transport) = test_session(std::time::Duration::from_secs(30));
+  let tr = TestRequest{test_req_id: "42".to_string()};
+  sess.handle_test_request(&tr).unwrap();
+  let out = transport.written();
+  assert!(out.contains("35=0\x01"));
+  assert!(out.contains(&format!("{}=42\x01", tags::TestReqID)));
+}
+
+#[cfg(feature = "std")]
 #[test]
 fn test_serialize() {
-  let msg : HashMap<i32, &str> = vec![(8,"FIX4.2"),(9,"1234"),(52,"BAH"),(54,"QUX"),(99,"FOOBAR")].into_iter().collect();
+  let msg : Vec<(i32, &str)> = vec![(8,"FIX4.2"),(9,"1234"),(52,"BAH"),(54,"QUX"),(99,"FOOBAR")];
   // assert_eq!(serialize(msg), "52=BAH\x0154=QUX\x0199=FOOBAR\x01");
   let buf = serialize("0", "SENDERCOMP", "TARGETCOMP", 1234, &msg);
   println!("{}", str::from_utf8(&buf).unwrap());
 }
 
+#[cfg(feature = "std")]
+#[test]
+fn test_fixencode_roundtrips_through_fixdecode() {
+  let buf = encode(&Heartbeat{}, "SENDERCOMP", "TARGETCOMP", 1234);
+  let fixstr = str::from_utf8(&buf).unwrap();
+  let (_, msg) = parse(fixstr).unwrap();
+  assert!(matches!(msg, Message::Heartbeat{..}));
+}
+
+/// A fixed `Clock`, standing in for the hardware RTC/host timestamp a
+/// `no_std` caller would supply in place of `ChronoClock`.
+#[cfg(test)]
+struct FixedClock;
+#[cfg(test)]
+impl Clock for FixedClock {
+  fn sending_time<'a>(&self, buf: &'a mut [u8]) -> &'a str {
+    let stamp = b"20260101-00:00:00.000";
+    buf[..stamp.len()].copy_from_slice(stamp);
+    str::from_utf8(&buf[..stamp.len()]).unwrap()
+  }
+}
+
+/// Exercises the `alloc`-only path `encode_with_clock`/`parse_unchecked`
+/// rely on (`BTreeMap`-backed `FixMap`, the `core::fmt::Write` `io_compat`
+/// shim, and a caller-supplied `Clock`), so it passes under
+/// `--no-default-features` as well as the default `std` build.
+#[test]
+fn test_encode_with_clock_roundtrips_without_std() {
+  let buf = encode_with_clock(&Heartbeat{}, "SENDERCOMP", "TARGETCOMP", 1234, &FixedClock);
+  let fixstr = str::from_utf8(&buf).unwrap();
+  let (_, msg) = parse_unchecked(fixstr).unwrap();
+  assert!(matches!(msg, Message::Heartbeat{..}));
+}
+